@@ -1,15 +1,20 @@
 use anyhow::Result;
-use image::{Rgba, RgbaImage};
+use arboard::{Clipboard, ImageData};
+use device_query::{DeviceQuery, DeviceState};
+use font8x8::UnicodeFonts;
+use image::RgbaImage;
 use pixels::{Pixels, SurfaceTexture};
 use rayon::prelude::*;
 use screenshots::Screen;
-use std::collections::HashSet;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 use winit::dpi::PhysicalSize;
 use winit::event::{
-    ElementState, Event, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent,
+    ElementState, Event, Ime, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode,
+    WindowEvent,
 };
 use winit::event_loop::{ControlFlow, EventLoop};
-use winit::window::{Fullscreen, WindowBuilder};
+use winit::window::{CursorIcon, Fullscreen, WindowBuilder};
 
 // Plattformspezifische Erweiterung für X11, um den Fenstertyp zu setzen
 #[cfg(any(
@@ -21,15 +26,77 @@ use winit::window::{Fullscreen, WindowBuilder};
 ))]
 use winit::platform::x11::{WindowBuilderExtX11, XWindowType};
 
-const BRUSH_SIZE: i32 = 5;
+const DEFAULT_BRUSH_SIZE: i32 = 5;
 const FOCUS_RADIUS: f64 = 125.0;
+const UNDO_HISTORY_LIMIT: usize = 50;
+// Unterhalb dieser Kantenlänge (in beiden Achsen) zählt ein Drag als Klick
+// ohne Auswahl und nimmt stattdessen den ganzen Bildschirm.
+const MIN_SELECTION_SIZE: f64 = 4.0;
+
+/// Phase, in der sich die App gerade befindet.
+enum AppPhase {
+    /// Der Nutzer zieht ein Rechteck über die gedimmte Vollbildaufnahme auf.
+    Selecting { anchor: Option<(f64, f64)> },
+    /// Die zugeschnittene Leinwand kann annotiert werden (bisheriges Verhalten).
+    Annotating,
+}
+
+/// Werkzeug, mit dem die nächste Annotation erzeugt wird.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Tool {
+    Brush,
+    Arrow,
+    Text,
+}
+
+/// Eine einzelne Annotation auf der Leinwand. Unabhängig von ihrer Art wird
+/// sie als rasterisierte Pixelabdeckung gespeichert, damit Radieren/Undo für
+/// Striche, Pfeile und Text identisch funktionieren.
+#[derive(Clone, PartialEq)]
+enum Annotation {
+    Stroke(HashMap<(u32, u32), f32>),
+    Arrow(HashMap<(u32, u32), f32>),
+    Text(HashMap<(u32, u32), f32>),
+}
+
+impl Annotation {
+    fn pixels(&self) -> &HashMap<(u32, u32), f32> {
+        match self {
+            Annotation::Stroke(p) | Annotation::Arrow(p) | Annotation::Text(p) => p,
+        }
+    }
+}
+
+/// Ein rückgängig machbarer Bearbeitungsschritt.
+enum Command {
+    /// Eine neu hinzugefügte Annotation (Strich, Pfeil oder Text).
+    Add(Annotation),
+    /// Die durch einen Radiervorgang entfernten Annotationen.
+    Erase(Vec<Annotation>),
+}
 
 /// Repräsentiert den Anwendungszustand.
 struct App {
+    phase: AppPhase,
+    screens: Vec<Screen>,
+    active_screen: usize,
+    // Annotationen pro Bildschirm, damit sie beim Wechsel nicht verloren gehen.
+    monitor_lines: Vec<Vec<Annotation>>,
+    // Zugeschnittenes Rechteck (x, y, w, h) pro Bildschirm, relativ zur
+    // Vollbildaufnahme, damit ein erneuter Wechsel denselben Ausschnitt
+    // reproduziert und die dazu gehörigen `monitor_lines`-Koordinaten gültig bleiben.
+    monitor_crops: Vec<Option<(u32, u32, u32, u32)>>,
     original_image: RgbaImage,
     leinwand: RgbaImage,
-    drawn_lines: Vec<Vec<(u32, u32)>>,
-    current_stroke: HashSet<(u32, u32)>, // Für die aktuelle, unfertige Linie
+    drawn_lines: Vec<Annotation>,
+    current_stroke: HashMap<(u32, u32), f32>, // Pixel -> maximale Kantenabdeckung [0,1] der aktuellen, unfertigen Linie
+    undo_stack: Vec<Command>,
+    redo_stack: Vec<Command>,
+    brush_size: i32,
+    active_tool: Tool,
+    arrow_anchor: Option<(f64, f64)>,
+    text_anchor: Option<(f64, f64)>,
+    text_buffer: String,
     zoom: f32,
     offset: (f32, f32),
     target_zoom: f32,
@@ -42,17 +109,39 @@ struct App {
     is_drawing: bool,
     is_erasing: bool,
     is_alt_pressed: bool,
+    is_ctrl_pressed: bool,
+    is_shift_pressed: bool,
+    // Ob Strg bereits als Akkord (z.B. Strg+Z) benutzt wurde, solange sie
+    // gehalten wird; unterdrückt das Umschalten des Zeichenmodus beim Loslassen.
+    ctrl_used_as_chord: bool,
+    // Ob das Fenster gerade IME-Events liefert; solange dem so ist, kommt Text
+    // ausschließlich über `Ime::Commit`, nicht über `ReceivedCharacter`.
+    ime_active: bool,
     last_paint_pos: Option<(f64, f64)>,
 }
 
 impl App {
-    fn new(image: RgbaImage) -> Self {
+    fn new(image: RgbaImage, screens: Vec<Screen>, active_screen: usize) -> Self {
         let leinwand = image.clone();
+        let monitor_lines = vec![Vec::new(); screens.len()];
+        let monitor_crops = vec![None; screens.len()];
         Self {
+            phase: AppPhase::Selecting { anchor: None },
+            screens,
+            active_screen,
+            monitor_lines,
+            monitor_crops,
             original_image: image,
             leinwand,
             drawn_lines: Vec::new(),
-            current_stroke: HashSet::new(),
+            current_stroke: HashMap::new(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            brush_size: DEFAULT_BRUSH_SIZE,
+            active_tool: Tool::Brush,
+            arrow_anchor: None,
+            text_anchor: None,
+            text_buffer: String::new(),
             zoom: 1.0,
             offset: (0.0, 0.0),
             target_zoom: 1.0,
@@ -64,12 +153,98 @@ impl App {
             is_drawing: false,
             is_erasing: false,
             is_alt_pressed: false,
+            is_ctrl_pressed: false,
+            is_shift_pressed: false,
+            ctrl_used_as_chord: false,
+            ime_active: false,
             last_paint_pos: None,
         }
     }
 
+    /// Schneidet `original_image`/`leinwand` auf das per Maus aufgezogene Rechteck
+    /// zu (oder auf den ganzen Schirm, wenn das Rechteck in beiden Achsen kleiner
+    /// als `MIN_SELECTION_SIZE` ist) und wechselt in die Annotationsphase.
+    fn finish_selection(&mut self, p0: (f64, f64), p1: (f64, f64)) {
+        let img_w = self.original_image.width();
+        let img_h = self.original_image.height();
+
+        // Ein einfacher Klick (oder ein winziges Zittern beim Loslassen) ergibt
+        // keine sinnvolle Auswahl; wie bei Enter den ganzen Bildschirm nehmen.
+        let (p0, p1) = if (p1.0 - p0.0).abs() < MIN_SELECTION_SIZE
+            && (p1.1 - p0.1).abs() < MIN_SELECTION_SIZE
+        {
+            ((0.0, 0.0), (img_w as f64, img_h as f64))
+        } else {
+            (p0, p1)
+        };
+
+        let x0 = p0.0.min(p1.0).clamp(0.0, img_w as f64) as u32;
+        let y0 = p0.1.min(p1.1).clamp(0.0, img_h as f64) as u32;
+        let x1 = p0.0.max(p1.0).clamp(0.0, img_w as f64) as u32;
+        let y1 = p0.1.max(p1.1).clamp(0.0, img_h as f64) as u32;
+        let w = x1.saturating_sub(x0).max(1).min(img_w - x0);
+        let h = y1.saturating_sub(y0).max(1).min(img_h - y0);
+
+        self.original_image = image::imageops::crop(&mut self.original_image, x0, y0, w, h).to_image();
+        self.leinwand = image::imageops::crop(&mut self.leinwand, x0, y0, w, h).to_image();
+        self.monitor_crops[self.active_screen] = Some((x0, y0, w, h));
+
+        self.zoom = 1.0;
+        self.offset = (0.0, 0.0);
+        self.target_zoom = 1.0;
+        self.target_offset = (0.0, 0.0);
+
+        self.phase = AppPhase::Annotating;
+    }
+
     /// Behandelt alle Eingaben und aktualisiert die Zielwerte für die Animation.
     fn input(&mut self, event: &WindowEvent) {
+        if matches!(self.phase, AppPhase::Selecting { .. }) {
+            match event {
+                WindowEvent::CursorMoved { position, .. } => {
+                    self.last_mouse_pos = (position.x, position.y);
+                }
+                WindowEvent::MouseInput {
+                    state,
+                    button: MouseButton::Left,
+                    ..
+                } => match state {
+                    ElementState::Pressed => {
+                        if let AppPhase::Selecting { anchor } = &mut self.phase {
+                            *anchor = Some(self.last_mouse_pos);
+                        }
+                    }
+                    ElementState::Released => {
+                        let anchor = match &self.phase {
+                            AppPhase::Selecting { anchor } => *anchor,
+                            _ => None,
+                        };
+                        if let Some(start) = anchor {
+                            let end = self.last_mouse_pos;
+                            self.finish_selection(start, end);
+                        }
+                    }
+                },
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: ElementState::Pressed,
+                            virtual_keycode: Some(VirtualKeyCode::Return),
+                            ..
+                        },
+                    ..
+                } => {
+                    let (w, h) = (
+                        self.original_image.width() as f64,
+                        self.original_image.height() as f64,
+                    );
+                    self.finish_selection((0.0, 0.0), (w, h));
+                }
+                _ => (),
+            }
+            return;
+        }
+
         match event {
             WindowEvent::MouseWheel { delta, .. } => {
                 let scroll = match delta {
@@ -90,31 +265,72 @@ impl App {
             WindowEvent::MouseInput { state, button, .. } => {
                 let pressed = *state == ElementState::Pressed;
                 if self.is_drawing_mode {
-                    match button {
-                        MouseButton::Left => self.is_drawing = pressed,
-                        MouseButton::Right => self.is_erasing = pressed,
-                        _ => (),
-                    }
-                    if pressed {
-                        self.last_paint_pos = Some(self.last_mouse_pos);
-                        if self.is_erasing {
-                            self.erase_at(self.last_mouse_pos.0, self.last_mouse_pos.1);
+                    match self.active_tool {
+                        Tool::Brush => {
+                            match button {
+                                MouseButton::Left => self.is_drawing = pressed,
+                                MouseButton::Right => self.is_erasing = pressed,
+                                _ => (),
+                            }
+                            if pressed {
+                                self.last_paint_pos = Some(self.last_mouse_pos);
+                                if self.is_erasing {
+                                    self.erase_at(self.last_mouse_pos.0, self.last_mouse_pos.1);
+                                }
+                            } else {
+                                // Maustaste losgelassen: Strich alphablend auf die Leinwand "einbrennen"
+                                if !self.current_stroke.is_empty() {
+                                    for (&(x, y), &coverage) in &self.current_stroke {
+                                        blend_pixel(&mut self.leinwand, x, y, [255, 0, 0], coverage);
+                                    }
+                                    let pixels: HashMap<(u32, u32), f32> =
+                                        self.current_stroke.drain().collect();
+                                    self.drawn_lines.push(Annotation::Stroke(pixels.clone()));
+                                    self.push_undo(Command::Add(Annotation::Stroke(pixels)));
+                                }
+                                self.last_paint_pos = None;
+                            }
                         }
-                    } else {
-                        // Maustaste losgelassen: Strich auf Leinwand "einbrennen"
-                        if !self.current_stroke.is_empty() {
-                            for &(x, y) in &self.current_stroke {
-                                self.leinwand.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+                        Tool::Arrow if *button == MouseButton::Left => {
+                            if pressed {
+                                self.arrow_anchor = Some(self.last_mouse_pos);
+                            } else if let Some(start) = self.arrow_anchor.take() {
+                                self.finish_arrow(start, self.last_mouse_pos);
                             }
-                            self.drawn_lines.push(self.current_stroke.drain().collect());
                         }
-                        self.last_paint_pos = None;
+                        Tool::Text if *button == MouseButton::Left && pressed => {
+                            self.finish_text_annotation();
+                            self.text_anchor = Some(self.last_mouse_pos);
+                        }
+                        _ => (),
                     }
                 } else if *button == MouseButton::Left {
                     self.is_panning = pressed;
                 }
             }
 
+            WindowEvent::ReceivedCharacter(c) => {
+                // Solange das Fenster IME zulässt, liefert `Ime::Commit` denselben
+                // Tastendruck noch einmal; sonst würde jedes Zeichen doppelt landen.
+                if self.text_anchor.is_some() && !self.ime_active && !c.is_control() {
+                    self.text_buffer.push(*c);
+                }
+            }
+
+            WindowEvent::Ime(Ime::Enabled) => {
+                self.ime_active = true;
+            }
+
+            WindowEvent::Ime(Ime::Disabled) => {
+                self.ime_active = false;
+            }
+
+            WindowEvent::Ime(Ime::Commit(s)) => {
+                if self.text_anchor.is_some() {
+                    self.text_buffer.push_str(s);
+                }
+            }
+
             WindowEvent::CursorMoved { position, .. } => {
                 let (new_x, new_y) = (position.x, position.y);
                 if self.is_panning {
@@ -141,19 +357,163 @@ impl App {
                     },
                 ..
             } => {
+                // Während einer laufenden Texteingabe dürfen nur Return/Back
+                // durchgreifen; jede andere Taste (z.B. Buchstaben, die auch
+                // über `ReceivedCharacter` in den `text_buffer` fließen) soll
+                // keine Shortcuts auslösen.
+                if self.text_anchor.is_some() {
+                    match keycode {
+                        VirtualKeyCode::Return if *state == ElementState::Pressed => {
+                            self.finish_text_annotation();
+                        }
+                        VirtualKeyCode::Back if *state == ElementState::Pressed => {
+                            self.text_buffer.pop();
+                        }
+                        _ => (),
+                    }
+                    return;
+                }
+
                 match keycode {
                     VirtualKeyCode::LControl => {
+                        self.is_ctrl_pressed = *state == ElementState::Pressed;
                         if *state == ElementState::Pressed {
+                            self.ctrl_used_as_chord = false;
+                        } else if !self.ctrl_used_as_chord {
+                            // Nur beim Loslassen umschalten, und nur wenn Strg
+                            // währenddessen nicht als Akkord (z.B. Strg+Z) diente.
                             self.is_drawing_mode = !self.is_drawing_mode;
                         }
                     }
                     VirtualKeyCode::LAlt => self.is_alt_pressed = *state == ElementState::Pressed,
+                    VirtualKeyCode::LShift => self.is_shift_pressed = *state == ElementState::Pressed,
+                    VirtualKeyCode::Z if *state == ElementState::Pressed && self.is_ctrl_pressed => {
+                        self.ctrl_used_as_chord = true;
+                        if self.is_shift_pressed {
+                            self.redo();
+                        } else {
+                            self.undo();
+                        }
+                    }
+                    VirtualKeyCode::S if *state == ElementState::Pressed => {
+                        if let Err(e) = self.export_png() {
+                            eprintln!("Fehler beim Speichern: {}", e);
+                        }
+                    }
+                    VirtualKeyCode::C if *state == ElementState::Pressed => {
+                        if let Err(e) = self.export_clipboard() {
+                            eprintln!("Fehler beim Kopieren in die Zwischenablage: {}", e);
+                        }
+                    }
+                    VirtualKeyCode::Tab if *state == ElementState::Pressed => {
+                        if let Err(e) = self.switch_to_next_monitor() {
+                            eprintln!("Fehler beim Wechseln des Bildschirms: {}", e);
+                        }
+                    }
+                    VirtualKeyCode::LBracket if *state == ElementState::Pressed => {
+                        self.brush_size = (self.brush_size - 1).max(1);
+                    }
+                    VirtualKeyCode::RBracket if *state == ElementState::Pressed => {
+                        self.brush_size = (self.brush_size + 1).min(200);
+                    }
+                    VirtualKeyCode::Key1 if *state == ElementState::Pressed => {
+                        self.active_tool = Tool::Brush;
+                    }
+                    VirtualKeyCode::Key2 if *state == ElementState::Pressed => {
+                        self.active_tool = Tool::Arrow;
+                    }
+                    VirtualKeyCode::Key3 if *state == ElementState::Pressed => {
+                        self.active_tool = Tool::Text;
+                    }
                     _ => (),
                 }
             }
             _ => (),
         }
     }
+
+    /// Erfasst den nächsten Bildschirm (zyklisch) neu, tauscht `original_image`
+    /// und `leinwand` aus und lädt die zu diesem Bildschirm gehörenden
+    /// Annotationen.
+    fn switch_to_next_monitor(&mut self) -> Result<()> {
+        if self.screens.len() < 2 {
+            return Ok(());
+        }
+
+        self.monitor_lines[self.active_screen] = std::mem::take(&mut self.drawn_lines);
+
+        self.active_screen = (self.active_screen + 1) % self.screens.len();
+        let mut image = self.screens[self.active_screen].capture()?;
+
+        // Falls dieser Bildschirm beim letzten Besuch zugeschnitten wurde, denselben
+        // Ausschnitt erneut anwenden, da `monitor_lines` in dessen Koordinaten steht.
+        if let Some((x, y, w, h)) = self.monitor_crops[self.active_screen] {
+            image = image::imageops::crop(&mut image, x, y, w, h).to_image();
+        }
+
+        self.drawn_lines = self.monitor_lines[self.active_screen].clone();
+        self.original_image = image;
+        self.rebuild_leinwand();
+
+        // Der Undo-Verlauf bezog sich auf den vorherigen Bildschirm.
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+
+        self.zoom = 1.0;
+        self.offset = (0.0, 0.0);
+        self.target_zoom = 1.0;
+        self.target_offset = (0.0, 0.0);
+
+        Ok(())
+    }
+
+    /// Anzeigeinformationen (Position/Größe) des gerade aktiven Bildschirms.
+    fn active_display_info(&self) -> screenshots::DisplayInfo {
+        self.screens[self.active_screen].display_info
+    }
+
+    /// Tatsächliche Größe der aktuellen Leinwand (kann kleiner als der Bildschirm
+    /// sein, wenn dieser Monitor zugeschnitten wurde).
+    fn canvas_dimensions(&self) -> (u32, u32) {
+        self.leinwand.dimensions()
+    }
+
+    /// Mauszeiger-Symbol passend zum aktuellen Modus.
+    fn cursor_icon(&self) -> CursorIcon {
+        if self.is_drawing_mode {
+            CursorIcon::Crosshair
+        } else {
+            CursorIcon::Default
+        }
+    }
+
+    /// Ob gerade eine Texteingabe läuft und das Fenster IME-Events annehmen soll.
+    fn wants_ime(&self) -> bool {
+        self.text_anchor.is_some()
+    }
+
+    /// Schreibt die gebackene Leinwand (inklusive eines eventuellen Crops) als
+    /// zeitgestempelte PNG-Datei ins Arbeitsverzeichnis.
+    fn export_png(&self) -> Result<()> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let path = format!("rugnify_{}.png", timestamp);
+        self.leinwand.save(&path)?;
+        eprintln!("Gespeichert: {}", path);
+        Ok(())
+    }
+
+    /// Kopiert die gebackene Leinwand als RGBA-Bild in die Systemzwischenablage.
+    fn export_clipboard(&self) -> Result<()> {
+        let (width, height) = self.leinwand.dimensions();
+        let mut clipboard = Clipboard::new()?;
+        clipboard.set_image(ImageData {
+            width: width as usize,
+            height: height as usize,
+            bytes: self.leinwand.as_raw().as_slice().into(),
+        })?;
+        eprintln!("In die Zwischenablage kopiert ({}x{})", width, height);
+        Ok(())
+    }
     
     /// Aktualisiert den Zustand für flüssige Animationen (wird in jedem Frame aufgerufen).
     fn update(&mut self) {
@@ -178,31 +538,96 @@ impl App {
         self.offset.1 += offset_y_diff * self.smoothing_factor;
     }
 
-    /// Löscht eine Linie, die vom Radierer berührt wird, und regeneriert die Leinwand.
+    /// Löscht Linien, die vom Radierer berührt werden, und regeneriert die Leinwand.
     fn erase_at(&mut self, screen_x: f64, screen_y: f64) {
-        let brush_radius = (BRUSH_SIZE as f32 / self.zoom).max(1.0);
+        let brush_radius = (self.brush_size as f32 / self.zoom).max(1.0);
         let img_center_x = screen_x as f32 / self.zoom + self.offset.0;
         let img_center_y = screen_y as f32 / self.zoom + self.offset.1;
+        let brush_radius_sq = brush_radius.powi(2);
 
-        let initial_line_count = self.drawn_lines.len();
+        let (keep, removed): (Vec<_>, Vec<_>) = std::mem::take(&mut self.drawn_lines)
+            .into_iter()
+            .partition(|annotation| {
+                !annotation.pixels().keys().any(|&(px, py)| {
+                    let dist_sq = (px as f32 - img_center_x).powi(2) + (py as f32 - img_center_y).powi(2);
+                    dist_sq <= brush_radius_sq
+                })
+            });
+        self.drawn_lines = keep;
 
-        self.drawn_lines.retain(|line| {
-            !line.iter().any(|(px, py)| {
-                let dist_sq =
-                    (*px as f32 - img_center_x).powi(2) + (*py as f32 - img_center_y).powi(2);
-                dist_sq <= brush_radius.powi(2)
-            })
-        });
+        // Wenn Annotationen entfernt wurden, regeneriere die Leinwand für korrekte
+        // Überlappungen und merke die Entfernung im Undo-Verlauf.
+        if !removed.is_empty() {
+            self.rebuild_leinwand();
+            self.push_undo(Command::Erase(removed));
+        }
+    }
 
-        // Wenn Linien entfernt wurden, regeneriere die Leinwand für korrekte Überlappungen.
-        if self.drawn_lines.len() < initial_line_count {
-            self.leinwand = self.original_image.clone();
-            for line in &self.drawn_lines {
-                for &(x, y) in line {
-                    self.leinwand.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+    /// Baut `leinwand` aus `original_image` und allen Annotationen neu auf.
+    fn rebuild_leinwand(&mut self) {
+        self.leinwand = self.original_image.clone();
+        for annotation in &self.drawn_lines {
+            for (&(x, y), &coverage) in annotation.pixels() {
+                blend_pixel(&mut self.leinwand, x, y, [255, 0, 0], coverage);
+            }
+        }
+    }
+
+    /// Merkt einen Bearbeitungsschritt im Undo-Verlauf vor und verwirft den Redo-Verlauf.
+    fn push_undo(&mut self, command: Command) {
+        self.redo_stack.clear();
+        self.undo_stack.push(command);
+        if self.undo_stack.len() > UNDO_HISTORY_LIMIT {
+            self.undo_stack.remove(0);
+        }
+    }
+
+    /// Macht die letzte Annotation oder den letzten Radiervorgang rückgängig.
+    fn undo(&mut self) {
+        let command = match self.undo_stack.pop() {
+            Some(command) => command,
+            None => return,
+        };
+        match command {
+            Command::Add(annotation) => {
+                // Nicht `pop()`: ein zwischenzeitlich rückgängig gemachtes
+                // Erase kann die betroffene Annotation wieder ans Ende von
+                // `drawn_lines` gehängt haben, sodass sie nicht mehr mit dem
+                // Kopf des Undo-Stacks übereinstimmt. Per Identität entfernen.
+                if let Some(pos) = self.drawn_lines.iter().position(|a| a == &annotation) {
+                    self.drawn_lines.remove(pos);
+                }
+                self.redo_stack.push(Command::Add(annotation));
+            }
+            Command::Erase(removed) => {
+                self.drawn_lines.extend(removed.iter().cloned());
+                self.redo_stack.push(Command::Erase(removed));
+            }
+        }
+        self.rebuild_leinwand();
+    }
+
+    /// Stellt den zuletzt rückgängig gemachten Schritt wieder her.
+    fn redo(&mut self) {
+        let command = match self.redo_stack.pop() {
+            Some(command) => command,
+            None => return,
+        };
+        match command {
+            Command::Add(annotation) => {
+                self.drawn_lines.push(annotation.clone());
+                self.undo_stack.push(Command::Add(annotation));
+            }
+            Command::Erase(removed) => {
+                for annotation in &removed {
+                    if let Some(pos) = self.drawn_lines.iter().position(|a| a == annotation) {
+                        self.drawn_lines.remove(pos);
+                    }
                 }
+                self.undo_stack.push(Command::Erase(removed));
             }
         }
+        self.rebuild_leinwand();
     }
 
     /// Zeichnet eine durchgehende Linie zwischen zwei Punkten.
@@ -237,41 +662,179 @@ impl App {
         }
     }
 
-    /// Fügt die Punkte eines runden Pinsels zum aktuellen Stroke hinzu.
+    /// Fügt die Punkte eines runden Pinsels zum aktuellen Stroke hinzu und
+    /// akkumuliert dabei für jedes Pixel die maximale Kantenglättungs-Abdeckung.
     fn add_brush_points(&mut self, screen_x: f64, screen_y: f64) {
-        let brush_radius = (BRUSH_SIZE as f32 / self.zoom).max(1.0);
+        let brush_radius = (self.brush_size as f32 / self.zoom).max(1.0);
         let img_center_x = screen_x as f32 / self.zoom + self.offset.0;
         let img_center_y = screen_y as f32 / self.zoom + self.offset.1;
-        
-        let start_x = (img_center_x - brush_radius).floor() as i32;
-        let end_x = (img_center_x + brush_radius).ceil() as i32;
-        let start_y = (img_center_y - brush_radius).floor() as i32;
-        let end_y = (img_center_y + brush_radius).ceil() as i32;
+
+        // Ein Pixel Rand zusätzlich abtasten, damit die weiche Kante mit erfasst wird.
+        let start_x = (img_center_x - brush_radius - 1.0).floor() as i32;
+        let end_x = (img_center_x + brush_radius + 1.0).ceil() as i32;
+        let start_y = (img_center_y - brush_radius - 1.0).floor() as i32;
+        let end_y = (img_center_y + brush_radius + 1.0).ceil() as i32;
 
         for x in start_x..=end_x {
             for y in start_y..=end_y {
-                let dist_sq = (x as f32 - img_center_x).powi(2) + (y as f32 - img_center_y).powi(2);
-                if dist_sq <= brush_radius.powi(2) {
-                    if x >= 0
-                        && y >= 0
-                        && x < self.original_image.width() as i32
-                        && y < self.original_image.height() as i32
-                    {
-                        self.current_stroke.insert((x as u32, y as u32));
+                if x < 0
+                    || y < 0
+                    || x >= self.original_image.width() as i32
+                    || y >= self.original_image.height() as i32
+                {
+                    continue;
+                }
+
+                let dist = ((x as f32 - img_center_x).powi(2) + (y as f32 - img_center_y).powi(2)).sqrt();
+                let coverage = (brush_radius + 0.5 - dist).clamp(0.0, 1.0);
+                if coverage > 0.0 {
+                    let entry = self.current_stroke.entry((x as u32, y as u32)).or_insert(0.0);
+                    *entry = entry.max(coverage);
+                }
+            }
+        }
+    }
+
+    /// Rasterisiert Schaft und Pfeilspitze in `current_stroke` (über die
+    /// vorhandene Bresenham-Linie aus `paint_line`), brennt sie auf die
+    /// Leinwand ein und legt sie als eigene Annotation ab.
+    fn finish_arrow(&mut self, start: (f64, f64), end: (f64, f64)) {
+        self.paint_line(start, end);
+
+        let shaft_angle = (end.1 - start.1).atan2(end.0 - start.0);
+        let head_len = (self.brush_size as f64 * 2.5).max(14.0);
+        for spread in [0.5_f64, -0.5] {
+            let head_angle = shaft_angle + std::f64::consts::PI - spread;
+            let head_point = (
+                end.0 + head_angle.cos() * head_len,
+                end.1 + head_angle.sin() * head_len,
+            );
+            self.paint_line(end, head_point);
+        }
+
+        if self.current_stroke.is_empty() {
+            return;
+        }
+        for (&(x, y), &coverage) in &self.current_stroke {
+            blend_pixel(&mut self.leinwand, x, y, [255, 0, 0], coverage);
+        }
+        let pixels: HashMap<(u32, u32), f32> = self.current_stroke.drain().collect();
+        self.drawn_lines.push(Annotation::Arrow(pixels.clone()));
+        self.push_undo(Command::Add(Annotation::Arrow(pixels)));
+    }
+
+    /// Rasterisiert den aktuellen `text_buffer` mit der eingebauten Bitmap-Schrift,
+    /// brennt ihn auf die Leinwand ein und legt ihn als eigene Annotation ab.
+    fn finish_text_annotation(&mut self) {
+        let anchor = match self.text_anchor.take() {
+            Some(anchor) => anchor,
+            None => return,
+        };
+        if self.text_buffer.is_empty() {
+            return;
+        }
+
+        let pixels = self.rasterize_text(anchor, &self.text_buffer);
+        for (&(x, y), &coverage) in &pixels {
+            blend_pixel(&mut self.leinwand, x, y, [255, 0, 0], coverage);
+        }
+        self.drawn_lines.push(Annotation::Text(pixels.clone()));
+        self.push_undo(Command::Add(Annotation::Text(pixels)));
+        self.text_buffer.clear();
+    }
+
+    /// Setzt einen Text anhand der gebündelten 8x8-Bitmap-Schrift (`font8x8`)
+    /// in eine Pixelabdeckung um, verankert bei `anchor`.
+    fn rasterize_text(&self, anchor: (f64, f64), text: &str) -> HashMap<(u32, u32), f32> {
+        let scale = (self.brush_size as f64 / 2.5).max(1.0);
+        let (img_w, img_h) = (self.original_image.width(), self.original_image.height());
+        let mut pixels = HashMap::new();
+
+        for (i, ch) in text.chars().enumerate() {
+            let glyph = match font8x8::BASIC_FONTS.get(ch) {
+                Some(glyph) => glyph,
+                None => continue,
+            };
+            let glyph_x = anchor.0 + i as f64 * 8.0 * scale;
+
+            for (row, bits) in glyph.iter().enumerate() {
+                for col in 0..8 {
+                    if bits & (1 << col) == 0 {
+                        continue;
+                    }
+                    let block_x = glyph_x + col as f64 * scale;
+                    let block_y = anchor.1 + row as f64 * scale;
+                    // Jedes gesetzte Font-Bit als scale x scale-Block malen, statt
+                    // nur einen einzelnen Pixel, sonst zerfällt die Schrift bei
+                    // scale > 1 in ein löchriges Punktraster.
+                    for dy in 0..scale.ceil() as i32 {
+                        for dx in 0..scale.ceil() as i32 {
+                            let px = block_x + dx as f64;
+                            let py = block_y + dy as f64;
+                            if px < 0.0 || py < 0.0 || px as u32 >= img_w || py as u32 >= img_h {
+                                continue;
+                            }
+                            pixels.insert((px as u32, py as u32), 1.0);
+                        }
                     }
                 }
             }
         }
+
+        pixels
     }
-    
+
+    /// Zeichnet die gedimmte Vollbildaufnahme mit der live gezogenen Auswahl
+    /// während der `Selecting`-Phase.
+    fn draw_selection(&self, pixels: &mut Pixels, frame_width: u32, anchor: Option<(f64, f64)>) {
+        let sel_rect = anchor.map(|(ax, ay)| {
+            let (mx, my) = self.last_mouse_pos;
+            (ax.min(mx), ay.min(my), ax.max(mx), ay.max(my))
+        });
+
+        let frame = pixels.frame_mut();
+        let (img_width, img_height) = self.original_image.dimensions();
+
+        frame
+            .par_chunks_mut(4)
+            .enumerate()
+            .for_each(|(i, pixel)| {
+                let screen_x = (i % frame_width as usize) as u32;
+                let screen_y = (i / frame_width as usize) as u32;
+
+                let mut color = [0x40, 0x40, 0x40, 0xff];
+                if screen_x < img_width && screen_y < img_height {
+                    color.copy_from_slice(&self.original_image.get_pixel(screen_x, screen_y).0);
+                }
+
+                let inside_selection = sel_rect.is_some_and(|(x0, y0, x1, y1)| {
+                    let (fx, fy) = (screen_x as f64, screen_y as f64);
+                    fx >= x0 && fx <= x1 && fy >= y0 && fy <= y1
+                });
+
+                if !inside_selection {
+                    color[0] = (color[0] as f32 * 0.25) as u8;
+                    color[1] = (color[1] as f32 * 0.25) as u8;
+                    color[2] = (color[2] as f32 * 0.25) as u8;
+                }
+
+                pixel.copy_from_slice(&color);
+            });
+    }
+
     /// Zeichnet den Frame. Diese Methode ist jetzt hochperformant.
     fn draw(&self, pixels: &mut Pixels, frame_width: u32, _frame_height: u32) {
-        // Erstelle ein temporäres Bild für diese Frame-Anzeige, um den langsamen HashSet-Lookup zu vermeiden.
+        if let AppPhase::Selecting { anchor } = &self.phase {
+            self.draw_selection(pixels, frame_width, *anchor);
+            return;
+        }
+
+        // Erstelle ein temporäres Bild für diese Frame-Anzeige, um den langsamen HashMap-Lookup zu vermeiden.
         let mut display_image = self.leinwand.clone();
 
-        // Zeichne den aktuellen, unfertigen Strich auf das temporäre Bild.
-        for &(x, y) in &self.current_stroke {
-            display_image.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+        // Zeichne den aktuellen, unfertigen Strich alphablend auf das temporäre Bild.
+        for (&(x, y), &coverage) in &self.current_stroke {
+            blend_pixel(&mut display_image, x, y, [255, 0, 0], coverage);
         }
 
         let frame = pixels.frame_mut();
@@ -311,23 +874,77 @@ impl App {
                     }
                 }
 
+                // Zeige im Zeichenmodus einen 1px-Ring, der die tatsächliche
+                // Pinselgröße auf dem Bildschirm markiert (Radierer in Weiß).
+                if self.is_drawing_mode {
+                    // `add_brush_points` rechnet mit `(brush_size / zoom).max(1.0)` in
+                    // Bildkoordinaten; mit `zoom` zurück auf den Bildschirm skaliert
+                    // ergibt das den Ringradius.
+                    let brush_screen_radius =
+                        ((self.brush_size as f32 / self.zoom).max(1.0) * self.zoom) as f64;
+                    let dist = ((screen_x as f64 - self.last_mouse_pos.0).powi(2)
+                        + (screen_y as f64 - self.last_mouse_pos.1).powi(2))
+                        .sqrt();
+                    if (dist - brush_screen_radius).abs() < 1.0 {
+                        color = if self.is_erasing {
+                            [0xff, 0xff, 0xff, 0xff]
+                        } else {
+                            [0xff, 0x00, 0x00, 0xff]
+                        };
+                    }
+                }
+
                 pixel.copy_from_slice(&color);
             });
     }
 }
 
+/// Blendet `color` mit der gegebenen Deckkraft `alpha` (0.0..=1.0) über den
+/// vorhandenen Pixel bei `(x, y)`: `out = src*a + dst*(1-a)` pro Kanal.
+fn blend_pixel(image: &mut RgbaImage, x: u32, y: u32, color: [u8; 3], alpha: f32) {
+    let dst = image.get_pixel_mut(x, y);
+    for c in 0..3 {
+        dst.0[c] = (color[c] as f32 * alpha + dst.0[c] as f32 * (1.0 - alpha)) as u8;
+    }
+    dst.0[3] = 255;
+}
+
 fn main() -> Result<()> {
     let screens = Screen::all()?;
-    let primary_screen = screens.get(0).ok_or_else(|| anyhow::anyhow!("Konnte keinen Bildschirm finden"))?;
-    let image_buffer = primary_screen.capture()?;
+    if screens.is_empty() {
+        return Err(anyhow::anyhow!("Konnte keinen Bildschirm finden"));
+    }
+
+    // Starte auf dem Bildschirm, auf dem sich der Mauszeiger gerade befindet.
+    let (cursor_x, cursor_y) = DeviceState::new().get_mouse().coords;
+    let active_screen = screens
+        .iter()
+        .position(|s| {
+            let info = s.display_info;
+            cursor_x >= info.x
+                && cursor_x < info.x + info.width as i32
+                && cursor_y >= info.y
+                && cursor_y < info.y + info.height as i32
+        })
+        .unwrap_or(0);
+
+    let image_buffer = screens[active_screen].capture()?;
     let (width, height) = image_buffer.dimensions();
 
     let event_loop = EventLoop::new();
-    
+
+    // Finde den `MonitorHandle`, der zum gewählten Bildschirm passt, damit das
+    // Vollbildfenster direkt auf dem richtigen Monitor erscheint.
+    let active_info = screens[active_screen].display_info;
+    let target_monitor = event_loop.available_monitors().find(|m| {
+        let pos = m.position();
+        pos.x == active_info.x && pos.y == active_info.y
+    });
+
     // Erstelle den WindowBuilder veränderbar, um plattformspezifische Optionen hinzuzufügen
     let mut builder = WindowBuilder::new()
         .with_decorations(false)
-        .with_fullscreen(Some(Fullscreen::Borderless(None)))
+        .with_fullscreen(Some(Fullscreen::Borderless(target_monitor)))
         .with_inner_size(PhysicalSize::new(width, height));
 
     // Setze den X11-Fenstertyp, um "floating" zu erzwingen
@@ -347,7 +964,7 @@ fn main() -> Result<()> {
     window.set_window_level(winit::window::WindowLevel::AlwaysOnTop);
     window.set_cursor_visible(true);
 
-    let mut app = App::new(image_buffer);
+    let mut app = App::new(image_buffer, screens, active_screen);
 
     let window_size = window.inner_size();
     let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
@@ -361,6 +978,8 @@ fn main() -> Result<()> {
                 event: win_event, ..
             } => {
                 app.input(&win_event);
+                window.set_cursor_icon(app.cursor_icon());
+                window.set_ime_allowed(app.wants_ime());
 
                 match win_event {
                     WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
@@ -378,6 +997,32 @@ fn main() -> Result<()> {
                             *control_flow = ControlFlow::Exit;
                         }
                     }
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(VirtualKeyCode::Tab),
+                                ..
+                            },
+                        ..
+                    } => {
+                        // `app.input` hat bereits neu erfasst; hier nur Fenster/Puffer
+                        // auf den neuen Bildschirm umstellen.
+                        let info = app.active_display_info();
+                        if let Some(monitor) = window.available_monitors().find(|m| {
+                            let pos = m.position();
+                            pos.x == info.x && pos.y == info.y
+                        }) {
+                            window.set_fullscreen(Some(Fullscreen::Borderless(Some(monitor))));
+                        }
+                        // Puffer anhand der tatsächlichen Leinwandgröße setzen, nicht
+                        // anhand des Monitors: ein zugeschnittener Bildschirm hat eine
+                        // kleinere Leinwand als `display_info` angibt.
+                        let (canvas_width, canvas_height) = app.canvas_dimensions();
+                        if let Err(e) = pixels.resize_buffer(canvas_width, canvas_height) {
+                            eprintln!("Fehler beim Ändern der Puffergröße: {}", e);
+                        }
+                    }
                     _ => (),
                 }
             }